@@ -0,0 +1,277 @@
+use crate::store::{SearchStrategy, VectorStore};
+use crate::vector::{DenseVector, Distances};
+
+use rand::Rng;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+// A single entry in a best-first search frontier: a candidate node and its
+// distance to the query. Ord is by distance so BinaryHeap gives us either a
+// min-heap (via Reverse) or a max-heap for free.
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    id: usize,
+    dist: f32,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.dist == other.dist
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.dist.partial_cmp(&other.dist).unwrap_or(Ordering::Equal)
+    }
+}
+
+/// A multi-layer proximity graph (Hierarchical Navigable Small World).
+///
+/// Every inserted vector lives in layers `0..=level`, where `level` is drawn
+/// from an exponential distribution so higher layers hold exponentially
+/// fewer nodes and act as express lanes down to layer 0. Layer 0 holds every
+/// vector and is where the final answer is found.
+#[derive(Debug, Clone)]
+pub struct HnswIndex {
+    m: usize,
+    ef_construction: usize,
+    level_mult: f32,
+    max_level: usize,
+    entry_point: Option<usize>,
+    // layers[l][node] = neighbor ids of `node` at layer l
+    layers: Vec<HashMap<usize, Vec<usize>>>,
+}
+
+impl HnswIndex {
+    pub fn new(m: usize, ef_construction: usize) -> Self {
+        HnswIndex {
+            m,
+            ef_construction,
+            level_mult: 1.0 / (m as f32).ln(),
+            max_level: 0,
+            entry_point: None,
+            layers: vec![HashMap::new()],
+        }
+    }
+
+    fn random_level(&self) -> usize {
+        let mut rng = rand::thread_rng();
+        let uniform: f32 = rng.gen::<f32>().max(f32::MIN_POSITIVE);
+        (-uniform.ln() * self.level_mult).floor() as usize
+    }
+
+    // Best-first search confined to a single layer, returning up to `ef`
+    // closest candidates to `query`, ascending by distance.
+    fn search_layer(
+        &self,
+        store: &VectorStore,
+        query: &DenseVector,
+        entry_points: &[usize],
+        ef: usize,
+        layer: usize,
+    ) -> Vec<Candidate> {
+        let mut visited: HashSet<usize> = entry_points.iter().cloned().collect();
+        let mut candidates: BinaryHeap<Reverse<Candidate>> = BinaryHeap::new();
+        let mut found: BinaryHeap<Candidate> = BinaryHeap::new();
+
+        for &ep in entry_points {
+            let cand = Candidate {
+                id: ep,
+                dist: store.dense[ep].distance(query, store.metric),
+            };
+            candidates.push(Reverse(cand));
+            found.push(cand);
+        }
+
+        while let Some(Reverse(current)) = candidates.pop() {
+            let worst = found.peek().map(|c| c.dist).unwrap_or(f32::MAX);
+            if current.dist > worst && found.len() >= ef {
+                break;
+            }
+
+            let neighbors = match self.layers[layer].get(&current.id) {
+                Some(neighbors) => neighbors.clone(),
+                None => continue,
+            };
+
+            for neighbor in neighbors {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+
+                let dist = store.dense[neighbor].distance(query, store.metric);
+                let worst = found.peek().map(|c| c.dist).unwrap_or(f32::MAX);
+
+                if found.len() < ef || dist < worst {
+                    candidates.push(Reverse(Candidate { id: neighbor, dist }));
+                    found.push(Candidate { id: neighbor, dist });
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+
+        found.into_sorted_vec()
+    }
+
+    // Diversification heuristic: keep a candidate only if it is closer to the
+    // query than to every neighbor already selected, so neighbors spread out
+    // around the node instead of clustering on one side.
+    fn select_neighbors(
+        &self,
+        store: &VectorStore,
+        candidates: Vec<Candidate>,
+        m: usize,
+    ) -> Vec<usize> {
+        let mut selected: Vec<Candidate> = Vec::new();
+
+        for candidate in candidates {
+            if selected.len() >= m {
+                break;
+            }
+
+            let is_diverse = selected.iter().all(|sel| {
+                candidate.dist < store.dense[candidate.id].distance(&store.dense[sel.id], store.metric)
+            });
+
+            if is_diverse {
+                selected.push(candidate);
+            }
+        }
+
+        selected.into_iter().map(|c| c.id).collect()
+    }
+
+    /// Insert `id` (an index into `store.dense`) into the graph.
+    pub fn insert(&mut self, store: &VectorStore, id: usize) {
+        let level = self.random_level();
+        while self.layers.len() <= level {
+            self.layers.push(HashMap::new());
+        }
+        for l in 0..=level {
+            self.layers[l].entry(id).or_default();
+        }
+
+        let entry_point = match self.entry_point {
+            Some(ep) => ep,
+            None => {
+                self.entry_point = Some(id);
+                self.max_level = level;
+                return;
+            }
+        };
+
+        let query = &store.dense[id];
+        let mut current_nearest = entry_point;
+
+        // Greedily descend from the top layer down to one above this node's
+        // level, keeping only the single best entry point at each step.
+        for l in (level + 1..=self.max_level).rev() {
+            if let Some(best) = self.search_layer(store, query, &[current_nearest], 1, l).first() {
+                current_nearest = best.id;
+            }
+        }
+
+        // From this node's level down to 0, do a proper ef_construction-wide
+        // search and wire up bidirectional neighbors at each layer.
+        let mut entry_points = vec![current_nearest];
+        for l in (0..=level.min(self.max_level)).rev() {
+            let found = self.search_layer(store, query, &entry_points, self.ef_construction, l);
+            let layer_cap = if l == 0 { self.m * 2 } else { self.m };
+            let neighbors = self.select_neighbors(store, found.clone(), layer_cap);
+
+            for &neighbor in &neighbors {
+                self.layers[l].entry(id).or_default().push(neighbor);
+
+                let reverse = self.layers[l].entry(neighbor).or_default();
+                reverse.push(id);
+
+                if reverse.len() > layer_cap {
+                    reverse.sort_by(|&a, &b| {
+                        let da = store.dense[neighbor].distance(&store.dense[a], store.metric);
+                        let db = store.dense[neighbor].distance(&store.dense[b], store.metric);
+                        da.partial_cmp(&db).unwrap_or(Ordering::Equal)
+                    });
+                    reverse.truncate(layer_cap);
+                }
+            }
+
+            entry_points = found.into_iter().map(|c| c.id).collect();
+        }
+
+        if level > self.max_level {
+            self.max_level = level;
+            self.entry_point = Some(id);
+        }
+    }
+}
+
+/// Graph-based approximate search (Best for large-scale data).
+///
+/// `ef` is the search-time beam width at layer 0: larger values trade
+/// latency for recall. Only the single closest node is returned: `search_k`
+/// uses the trait's default and yields at most one result.
+pub struct HNSWSearch {
+    pub ef: usize,
+}
+
+impl SearchStrategy for HNSWSearch {
+    fn search(&self, store: &VectorStore, query_vec: &DenseVector) -> Option<(usize, f32)> {
+        let index = store.hnsw_index.as_ref()?;
+        let entry_point = index.entry_point?;
+
+        let mut current_nearest = entry_point;
+        for l in (1..=index.max_level).rev() {
+            if let Some(best) = index
+                .search_layer(store, query_vec, &[current_nearest], 1, l)
+                .first()
+            {
+                current_nearest = best.id;
+            }
+        }
+
+        let ef = self.ef.max(1);
+        index
+            .search_layer(store, query_vec, &[current_nearest], ef, 0)
+            .into_iter()
+            .next()
+            .map(|c| (c.id, c.dist))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{BruteForceSearch, VectorStore};
+
+    // On a small, fully-connected graph (ef_construction covers every
+    // vector) HNSW should find exactly the same nearest neighbor as brute
+    // force -- this is the recall floor the approximate search is built on.
+    #[test]
+    fn test_hnsw_matches_brute_force_on_small_store() {
+        let mut store = VectorStore::new();
+        for i in 0..20 {
+            let i = i as f32;
+            store.add(DenseVector::new(vec![i, i * 2.0, -i, i * 0.5]));
+        }
+        store.build_hnsw(8, 20);
+
+        let query = DenseVector::new(vec![9.3, 18.6, -9.3, 4.65]);
+
+        let brute = store.search(&query, BruteForceSearch).unwrap();
+        let hnsw = store.search(&query, HNSWSearch { ef: 20 }).unwrap();
+
+        assert_eq!(hnsw.0, brute.0);
+        assert!((hnsw.1 - brute.1).abs() < 1e-5);
+    }
+}
@@ -1,16 +1,27 @@
+mod hnsw;
 mod math;
+mod mmap_store;
+mod pq;
 mod store;
 mod vector;
 
 use std::time::Instant;
 use rand::Rng;
-use store::{VectorStore, BruteForceSearch, IVFSearch};
+use hnsw::HNSWSearch;
+use store::{VectorStore, BruteForceSearch, IVFPQSearch, IVFSearch};
 use vector::DenseVector;
 
 const NUM_VECTORS: usize = 500_000;
 const VECTOR_DIM: usize = 64;
 const NUM_CLUSTERS: usize = 100; // sqrt(N) is a common rule of thumb, but 100 is good for testing
 const MAX_ITER: usize = 10;
+const HNSW_M: usize = 16;
+const HNSW_EF_CONSTRUCTION: usize = 200;
+const HNSW_EF_SEARCH: usize = 50;
+const PQ_SUBSPACES: usize = 8; // VECTOR_DIM must be divisible by this
+const PQ_CENTROIDS: usize = 256; // one byte per sub-code
+const PQ_RERANK: usize = 10;
+const IVF_NPROBE: usize = 4;
 
 fn main() {
     let mut store = VectorStore::new();
@@ -24,7 +35,7 @@ fn main() {
             .map(|_| rng.gen_range(-1.0..1.0))
             .collect();
         
-        store.add(DenseVector { elements });
+        store.add(DenseVector::new(elements));
 
         if (i + 1) % 50_000 == 0 {
             println!("  Loaded {} vectors...", i + 1);
@@ -37,11 +48,27 @@ fn main() {
     store.build_index(NUM_CLUSTERS, MAX_ITER);
     println!("Index built in {:.2?}", start_train.elapsed());
 
+    println!(
+        "\nBuilding HNSW Index (m={}, ef_construction={})...",
+        HNSW_M, HNSW_EF_CONSTRUCTION
+    );
+    let start_hnsw = Instant::now();
+    store.build_hnsw(HNSW_M, HNSW_EF_CONSTRUCTION);
+    println!("HNSW index built in {:.2?}", start_hnsw.elapsed());
+
+    println!(
+        "\nTraining Product Quantizer (m={}, k={})...",
+        PQ_SUBSPACES, PQ_CENTROIDS
+    );
+    let start_pq = Instant::now();
+    store.build_pq(PQ_SUBSPACES, PQ_CENTROIDS, MAX_ITER);
+    println!("PQ codebooks trained in {:.2?}", start_pq.elapsed());
+
     // Create a random query vector
     let query_elements: Vec<f32> = (0..VECTOR_DIM)
         .map(|_| rng.gen_range(-1.0..1.0))
         .collect();
-    let query = DenseVector { elements: query_elements };
+    let query = DenseVector::new(query_elements);
 
     println!("\n--- Benchmarking Search ---");
 
@@ -54,11 +81,25 @@ fn main() {
 
     // Method B: IVF
     let start_ivf = Instant::now();
-    let result_ivf = store.search(&query, IVFSearch);
+    let result_ivf = store.search(&query, IVFSearch { nprobe: IVF_NPROBE });
     let duration_ivf = start_ivf.elapsed();
     println!("IVF Search:  Found closest in {:.2?}", duration_ivf);
     // println!("  Result: {:?}", result_ivf);
 
+    // Method C: HNSW
+    let start_hnsw_search = Instant::now();
+    let result_hnsw = store.search(&query, HNSWSearch { ef: HNSW_EF_SEARCH });
+    let duration_hnsw = start_hnsw_search.elapsed();
+    println!("HNSW Search: Found closest in {:.2?}", duration_hnsw);
+    // println!("  Result: {:?}", result_hnsw);
+
+    // Method D: IVF + Product Quantization
+    let start_ivfpq = Instant::now();
+    let result_ivfpq = store.search(&query, IVFPQSearch { rerank: PQ_RERANK });
+    let duration_ivfpq = start_ivfpq.elapsed();
+    println!("IVFPQ Search: Found closest in {:.2?}", duration_ivfpq);
+    // println!("  Result: {:?}", result_ivfpq);
+
     // Comparison
     if duration_ivf.as_micros() > 0 {
         let speedup = duration_bf.as_secs_f32() / duration_ivf.as_secs_f32();
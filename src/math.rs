@@ -1,6 +1,7 @@
-use crate::vector::{DenseVector, Distances};
+use crate::vector::{DenseVector, Distances, Metric};
 
 use rand::seq::SliceRandom; // For random sampling
+use std::cmp::Ordering;
 
 pub fn euclidean_distance(a: &[f32], b: &[f32]) -> f32 {
     // debug_assert! checks lengths ONLY during development.
@@ -34,12 +35,30 @@ pub fn mean_vector(vectors: &[DenseVector]) -> DenseVector {
     let count = vectors.len() as f32;
     let mean_elements = sum_elements.iter().map(|val| val / count).collect();
 
-    DenseVector {
-        elements: mean_elements,
+    DenseVector::new(mean_elements)
+}
+
+/// Returns the index of the candidate closest to `query` under `metric`.
+pub fn nearest_vector_index(query: &DenseVector, candidates: &[DenseVector], metric: Metric) -> usize {
+    candidates
+        .iter()
+        .map(|candidate| candidate.distance(query, metric))
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+        .map(|(idx, _)| idx)
+        .unwrap_or(0)
+}
+
+// Rescales `vector` onto the unit sphere in place; a no-op on a zero vector.
+fn normalize(vector: &mut DenseVector) {
+    let norm: f32 = vector.elements.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        let rescaled = vector.elements.iter().map(|x| x / norm).collect();
+        vector.set_elements(rescaled);
     }
 }
 
-pub fn kmeans(vectors: &[DenseVector], k: usize, max_iters: usize) -> Vec<DenseVector> {
+pub fn kmeans(vectors: &[DenseVector], k: usize, max_iters: usize, metric: Metric) -> Vec<DenseVector> {
     let mut rng = rand::thread_rng();
 
     // Initialize the centroids -> Pick 'k' random vectors from our list to start
@@ -53,29 +72,172 @@ pub fn kmeans(vectors: &[DenseVector], k: usize, max_iters: usize) -> Vec<DenseV
             // Find index of closest centroid to v
             let closest_index = centroids
                 .iter()
-                .map(|centroid| centroid.distance(&v))
+                .map(|centroid| centroid.distance(v, metric))
                 .enumerate()
-                .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+                .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
                 .map(|(indx, _)| indx)
                 .unwrap();
-            
+
             // Add v to the closest group
             clusters[closest_index].push(v.clone());
         }
-        
+
+        // ELBG-style repair: donate points from the highest-distortion
+        // cluster into any cluster that ended up empty, instead of
+        // collapsing it onto an existing centroid.
+        repair_empty_clusters(&mut clusters, max_iters, metric);
+
         // Move centroids to the average of their group
-        let new_centroids: Vec<DenseVector> = clusters.iter().map(|cluster| {
-            if cluster.is_empty() {
-                // if centroid has no surrounding vectors, they stay put
-                // TODO: update logic to pick a new random spot
-                centroids[0].clone()
-            } else {
-                mean_vector(cluster)
-            }
-        }).collect();
-        
+        let new_centroids: Vec<DenseVector> = clusters
+            .iter()
+            .enumerate()
+            .map(|(i, cluster)| {
+                if cluster.is_empty() {
+                    // Repair couldn't find a donor to split (e.g. fewer
+                    // distinct points than clusters); leave it in place.
+                    centroids[i].clone()
+                } else {
+                    let mut centroid = mean_vector(cluster);
+                    // Cosine similarity only cares about direction, so keep
+                    // centroids on the unit sphere as they're re-averaged.
+                    if metric == Metric::Cosine {
+                        normalize(&mut centroid);
+                    }
+                    centroid
+                }
+            })
+            .collect();
+
         centroids = new_centroids;
     }
 
     centroids
 }
+
+// Sum of squared distances from every member to the cluster's mean --
+// a measure of how spread out (distorted) the cluster is.
+fn distortion(cluster: &[DenseVector], metric: Metric) -> f32 {
+    if cluster.is_empty() {
+        return 0.0;
+    }
+
+    let centroid = mean_vector(cluster);
+    cluster.iter().map(|v| v.distance(&centroid, metric).powi(2)).sum()
+}
+
+// Finds the two members of `cluster` that are farthest apart from each
+// other; used as split seeds when a donor cluster is divided in two.
+fn two_farthest_members(cluster: &[DenseVector], metric: Metric) -> (DenseVector, DenseVector) {
+    let mut best = (cluster[0].clone(), cluster[1].clone());
+    let mut best_dist = cluster[0].distance(&cluster[1], metric);
+
+    for i in 0..cluster.len() {
+        for j in (i + 1)..cluster.len() {
+            let d = cluster[i].distance(&cluster[j], metric);
+            if d > best_dist {
+                best_dist = d;
+                best = (cluster[i].clone(), cluster[j].clone());
+            }
+        }
+    }
+
+    best
+}
+
+// Repeatedly splits the highest-distortion cluster to repopulate any empty
+// clusters, as long as a split actually reduces distortion. This keeps
+// every cluster populated instead of collapsing empties onto `centroids[0]`.
+fn repair_empty_clusters(clusters: &mut [Vec<DenseVector>], max_iters: usize, metric: Metric) {
+    for _ in 0..max_iters {
+        let empty_indices: Vec<usize> = clusters
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_empty())
+            .map(|(i, _)| i)
+            .collect();
+
+        if empty_indices.is_empty() {
+            break;
+        }
+
+        let mut progressed = false;
+
+        for empty_index in empty_indices {
+            if !clusters[empty_index].is_empty() {
+                continue; // already repopulated by an earlier split this pass
+            }
+
+            let donor_index = clusters
+                .iter()
+                .enumerate()
+                .filter(|(i, c)| *i != empty_index && c.len() >= 2)
+                .max_by(|(_, a), (_, b)| {
+                    distortion(a, metric)
+                        .partial_cmp(&distortion(b, metric))
+                        .unwrap_or(Ordering::Equal)
+                })
+                .map(|(i, _)| i);
+
+            let donor_index = match donor_index {
+                Some(i) => i,
+                None => continue, // nothing left big enough to split
+            };
+
+            let before = distortion(&clusters[donor_index], metric);
+            let (seed_a, seed_b) = two_farthest_members(&clusters[donor_index], metric);
+            let donor_members = std::mem::take(&mut clusters[donor_index]);
+
+            let (group_a, group_b): (Vec<DenseVector>, Vec<DenseVector>) = donor_members
+                .into_iter()
+                .partition(|v| v.distance(&seed_a, metric) <= v.distance(&seed_b, metric));
+
+            if group_a.is_empty() || group_b.is_empty() {
+                // Donor couldn't usefully split (e.g. coincident points);
+                // restore it untouched and leave this cluster empty.
+                clusters[donor_index] = group_a.into_iter().chain(group_b).collect();
+                continue;
+            }
+
+            if distortion(&group_a, metric) + distortion(&group_b, metric) < before {
+                progressed = true;
+                clusters[donor_index] = group_a;
+                clusters[empty_index] = group_b;
+            } else {
+                // Split didn't actually reduce distortion; restore the donor
+                // untouched rather than keeping a worsening split just
+                // because it happens to repopulate `empty_index`.
+                clusters[donor_index] = group_a.into_iter().chain(group_b).collect();
+            }
+        }
+
+        if !progressed {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A donor cluster with enough spread to split cleanly should repopulate
+    // every empty cluster, leaving none behind.
+    #[test]
+    fn test_repair_empty_clusters_leaves_none_empty() {
+        let donor = vec![
+            DenseVector::new(vec![0.0, 0.0]),
+            DenseVector::new(vec![0.1, 0.0]),
+            DenseVector::new(vec![10.0, 10.0]),
+            DenseVector::new(vec![10.1, 10.0]),
+        ];
+        let mut clusters: Vec<Vec<DenseVector>> = vec![donor, Vec::new(), Vec::new()];
+
+        repair_empty_clusters(&mut clusters, 10, Metric::L2);
+
+        assert!(
+            clusters.iter().all(|c| !c.is_empty()),
+            "repair left an empty cluster: {clusters:?}"
+        );
+        assert_eq!(clusters.iter().map(|c| c.len()).sum::<usize>(), 4);
+    }
+}
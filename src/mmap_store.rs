@@ -0,0 +1,59 @@
+use memmap2::Mmap;
+
+use std::fs::File;
+use std::io::{self, Read};
+
+// [num_vectors:u64][dim:u64] followed by contiguous little-endian f32 rows,
+// matching the header `VectorStore::save_to_disk` writes.
+const HEADER_LEN: usize = 16;
+
+/// A read-only, page-fault-driven view over the vector rows of an on-disk
+/// store (in the spirit of DiskANN's `MmapArray`). Only the header is
+/// parsed eagerly; row bytes are faulted in by the OS as `vector()` is
+/// called, so the working set no longer needs to fit in RAM.
+#[derive(Debug)]
+pub struct MmapVectors {
+    mmap: Mmap,
+    pub num_vectors: usize,
+    pub dim: usize,
+}
+
+impl MmapVectors {
+    pub fn open(path: &str) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+
+        let mut header = [0u8; HEADER_LEN];
+        file.read_exact(&mut header)?;
+        let num_vectors = u64::from_le_bytes(header[0..8].try_into().unwrap()) as usize;
+        let dim = u64::from_le_bytes(header[8..16].try_into().unwrap()) as usize;
+
+        // Safety: the mapped file is expected to stay untouched by other
+        // processes for the lifetime of the mapping, the standard mmap
+        // caveat.
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        Ok(MmapVectors {
+            mmap,
+            num_vectors,
+            dim,
+        })
+    }
+
+    /// The offset into the file where the vector data region ends (and the
+    /// trailing metric byte, followed by any companion index section, begins).
+    pub fn data_end(&self) -> u64 {
+        (HEADER_LEN + self.num_vectors * self.dim * 4) as u64
+    }
+
+    /// Zero-copy slice into the mapping for row `i`.
+    pub fn vector(&self, i: usize) -> &[f32] {
+        let row_bytes = self.dim * 4;
+        let start = HEADER_LEN + i * row_bytes;
+        let bytes = &self.mmap[start..start + row_bytes];
+
+        // Safe: rows are written as contiguous little-endian f32s and we
+        // index within the mapped bounds; this assumes a little-endian
+        // host, true of every platform this crate targets.
+        unsafe { std::slice::from_raw_parts(bytes.as_ptr() as *const f32, self.dim) }
+    }
+}
@@ -1,31 +1,107 @@
+use crate::hnsw::HnswIndex;
 use crate::math::{kmeans, nearest_vector_index};
-use crate::vector::{DenseVector, Distances, QuantizedVector};
+use crate::mmap_store::MmapVectors;
+use crate::pq::ProductQuantizer;
+use crate::vector::{DenseVector, Distances, Metric, QuantizedVector, metric_distance};
 
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
 use std::fs::File;
-use std::io::{BufReader, BufWriter, Read, Write};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 
 pub trait SearchStrategy {
     fn search(&self, store: &VectorStore, query_vec: &DenseVector) -> Option<(usize, f32)>;
+
+    /// Returns up to `k` nearest neighbors, ascending by distance. The
+    /// default falls back to the single best result from `search`;
+    /// strategies that can cheaply rank multiple candidates (brute force,
+    /// IVF) override this directly.
+    fn search_k(&self, store: &VectorStore, query_vec: &DenseVector, k: usize) -> Vec<(usize, f32)> {
+        let _ = k;
+        self.search(store, query_vec).into_iter().collect()
+    }
+}
+
+// A candidate paired with its distance, ordered by distance so it can back
+// a bounded max-heap: the worst of the current top-k sits at the top and is
+// the first thing evicted when a closer candidate shows up.
+#[derive(Debug, Clone, Copy)]
+struct Scored {
+    idx: usize,
+    dist: f32,
+}
+
+impl PartialEq for Scored {
+    fn eq(&self, other: &Self) -> bool {
+        self.dist == other.dist
+    }
+}
+
+impl Eq for Scored {}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.dist.partial_cmp(&other.dist).unwrap_or(Ordering::Equal)
+    }
+}
+
+// Drains `candidates` into the k closest, sorted ascending by distance,
+// using a max-heap bounded at size k so we never hold more than k scores.
+fn top_k(candidates: impl Iterator<Item = (usize, f32)>, k: usize) -> Vec<(usize, f32)> {
+    let mut heap: BinaryHeap<Scored> = BinaryHeap::with_capacity(k);
+
+    for (idx, dist) in candidates {
+        if heap.len() < k {
+            heap.push(Scored { idx, dist });
+        } else if let Some(worst) = heap.peek() {
+            if dist < worst.dist {
+                heap.pop();
+                heap.push(Scored { idx, dist });
+            }
+        }
+    }
+
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|s| (s.idx, s.dist))
+        .collect()
 }
 
 // Index algorithms
-// pub struct HNSWSearch; // Best for large-scale data
-// pub struct IVFPQSearch;
-pub struct IVFSearch;
+/// IVF multi-probe: ranks every centroid by distance to the query and scans
+/// the candidate lists of the `nprobe` closest buckets, merging them before
+/// ranking. `nprobe = 1` matches the original single-bucket behavior;
+/// raising it trades latency for recall near cluster boundaries.
+pub struct IVFSearch {
+    pub nprobe: usize,
+}
 pub struct BruteForceSearch; // Flat index (100% accuracy but speed tradeoff)
 
+/// IVF + Product Quantization: only the nearest bucket's candidates are
+/// scanned, and they're ranked with cheap PQ table lookups instead of full
+/// dense math. Set `rerank` to re-score the top few candidates with exact
+/// distances once the PQ shortlist narrows things down; `0` skips reranking.
+pub struct IVFPQSearch {
+    pub rerank: usize,
+}
+
 impl SearchStrategy for BruteForceSearch {
     fn search(&self, store: &VectorStore, query_vec: &DenseVector) -> Option<(usize, f32)> {
-        let quant_query = QuantizedVector {
-            elements: query_vec.elements.iter().take(2).cloned().collect(),
-        };
+        if store.is_empty() {
+            return None;
+        }
 
         let mut best_distance = f32::MAX;
         let mut best_index = 0;
 
-        for (i, v) in store.quantized.iter().enumerate() {
-            let dist = v.distance(&quant_query);
+        for i in 0..store.len() {
+            let dist = metric_distance(store.vector(i), &query_vec.elements, store.metric);
 
             if dist < best_distance {
                 best_distance = dist;
@@ -33,57 +109,172 @@ impl SearchStrategy for BruteForceSearch {
             }
         }
 
-        let precise_distance = store.dense[best_index].distance(query_vec);
+        Some((best_index, best_distance))
+    }
 
-        Some((best_index, precise_distance))
+    fn search_k(&self, store: &VectorStore, query_vec: &DenseVector, k: usize) -> Vec<(usize, f32)> {
+        top_k(
+            (0..store.len()).map(|i| (i, metric_distance(store.vector(i), &query_vec.elements, store.metric))),
+            k,
+        )
     }
 }
 
-impl SearchStrategy for IVFSearch {
+impl SearchStrategy for IVFPQSearch {
     fn search(&self, store: &VectorStore, query_vec: &DenseVector) -> Option<(usize, f32)> {
-        if store.centroids.is_none() || store.ivf_index.is_none() {
-            eprintln!("IVF Index not built! Call build_index() first.");
+        if store.centroids.is_none() || store.ivf_index.is_none() || store.pq.is_none() {
+            eprintln!("IVFPQ Index not built! Call build_index() and build_pq() first.");
             return None;
         }
 
         let centroids = store.centroids.as_ref().unwrap();
         let index = store.ivf_index.as_ref().unwrap();
+        let pq = store.pq.as_ref().unwrap();
 
         // Find the nearest centroid (The "Bucket")
-        let best_centroid_index = nearest_vector_index(query_vec, centroids);
+        let best_centroid_index = nearest_vector_index(query_vec, centroids, store.metric);
 
-        // Retrieve candidate indices from that bucket
-        // If the bucket is empty/missing, return None
-        let candidate_indices = match index.get(&best_centroid_index) {
-            Some(indices) => indices,
-            None => return None, 
-        };
+        let candidate_indices = index.get(&best_centroid_index)?;
 
-        // 4. Search ONLY the candidates in this bucket
-        let mut best_distance = f32::MAX;
-        let mut best_index = usize::MAX;
+        // Rank every candidate by cheap PQ table lookups
+        let table = pq.distance_table(query_vec);
+        let mut ranked: Vec<(usize, f32)> = candidate_indices
+            .iter()
+            .map(|&idx| (idx, pq.asymmetric_distance(&table, &store.quantized[idx].codes)))
+            .collect();
 
-        for &idx in candidate_indices {
-            // We jump straight to the dense vector in the main storage
-            let candidate_vec = &store.dense[idx];
-            let dist = candidate_vec.distance(query_vec);
+        ranked.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
 
-            if dist < best_distance {
-                best_distance = dist;
-                best_index = idx;
+        if self.rerank == 0 {
+            return ranked.into_iter().next();
+        }
+
+        // Re-rank the top few PQ candidates with exact distances
+        ranked
+            .into_iter()
+            .take(self.rerank)
+            .map(|(idx, _)| (idx, store.dense[idx].distance(query_vec, store.metric)))
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+    }
+}
+
+impl IVFSearch {
+    // Ranks every centroid by distance to the query and merges the member
+    // lists of the `nprobe` closest buckets into one candidate pool.
+    fn probe_candidates(&self, store: &VectorStore, query_vec: &DenseVector) -> Option<Vec<usize>> {
+        let centroids = store.centroids.as_ref()?;
+        let index = store.ivf_index.as_ref()?;
+
+        let mut ranked_centroids: Vec<(usize, f32)> = centroids
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (i, c.distance(query_vec, store.metric)))
+            .collect();
+        ranked_centroids.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+
+        let nprobe = self.nprobe.max(1).min(centroids.len());
+        let mut candidates = Vec::new();
+
+        for &(centroid_idx, _) in ranked_centroids.iter().take(nprobe) {
+            if let Some(bucket) = index.get(&centroid_idx) {
+                candidates.extend_from_slice(bucket);
             }
         }
 
-        // If we found nothing (empty bucket), return None
-        if best_index == usize::MAX {
-            None
-        } else {
-            Some((best_index, best_distance))
+        Some(candidates)
+    }
+}
+
+impl SearchStrategy for IVFSearch {
+    fn search(&self, store: &VectorStore, query_vec: &DenseVector) -> Option<(usize, f32)> {
+        if store.centroids.is_none() || store.ivf_index.is_none() {
+            eprintln!("IVF Index not built! Call build_index() first.");
+            return None;
         }
+
+        let candidate_indices = self.probe_candidates(store, query_vec)?;
+
+        // Read each candidate row on demand (a page fault for mmap-backed
+        // stores) rather than materializing every vector up front.
+        candidate_indices
+            .into_iter()
+            .map(|idx| (idx, metric_distance(store.vector(idx), &query_vec.elements, store.metric)))
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))
     }
+
+    fn search_k(&self, store: &VectorStore, query_vec: &DenseVector, k: usize) -> Vec<(usize, f32)> {
+        if store.centroids.is_none() || store.ivf_index.is_none() {
+            eprintln!("IVF Index not built! Call build_index() first.");
+            return Vec::new();
+        }
+
+        let candidate_indices = match self.probe_candidates(store, query_vec) {
+            Some(indices) => indices,
+            None => return Vec::new(),
+        };
+
+        top_k(
+            candidate_indices
+                .into_iter()
+                .map(|idx| (idx, metric_distance(store.vector(idx), &query_vec.elements, store.metric))),
+            k,
+        )
+    }
+}
+
+// Trained centroids plus the cluster-id -> member-list buckets that make up
+// an IVF index, as restored off disk.
+type IvfSection = (Vec<DenseVector>, HashMap<usize, Vec<usize>>);
+
+// Parses the companion IVF section `save_to_disk` writes right after the
+// metric byte: a `0`/`1` flag, and when `1`, the trained centroids and
+// cluster-id -> member-list buckets. Shared by `load_from_disk` and
+// `open_mmap` so both restore paths agree on the on-disk layout.
+fn read_ivf_section(reader: &mut impl Read, dim: usize) -> std::io::Result<Option<IvfSection>> {
+    let mut flag = [0u8; 1];
+    reader.read_exact(&mut flag)?;
+
+    if flag[0] != 1 {
+        return Ok(None);
+    }
+
+    let row_bytes = dim * 4;
+    let mut count_buf = [0u8; 8];
+
+    reader.read_exact(&mut count_buf)?;
+    let num_centroids = u64::from_le_bytes(count_buf) as usize;
+
+    let mut centroids = Vec::with_capacity(num_centroids);
+    for _ in 0..num_centroids {
+        let mut buf = vec![0u8; row_bytes];
+        reader.read_exact(&mut buf)?;
+        centroids.push(DenseVector::from_bytes(&buf));
+    }
+
+    reader.read_exact(&mut count_buf)?;
+    let num_buckets = u64::from_le_bytes(count_buf) as usize;
+
+    let mut index: HashMap<usize, Vec<usize>> = HashMap::new();
+    for _ in 0..num_buckets {
+        reader.read_exact(&mut count_buf)?;
+        let cluster_id = u64::from_le_bytes(count_buf) as usize;
+
+        reader.read_exact(&mut count_buf)?;
+        let bucket_len = u64::from_le_bytes(count_buf) as usize;
+
+        let mut members = Vec::with_capacity(bucket_len);
+        for _ in 0..bucket_len {
+            reader.read_exact(&mut count_buf)?;
+            members.push(u64::from_le_bytes(count_buf) as usize);
+        }
+
+        index.insert(cluster_id, members);
+    }
+
+    Ok(Some((centroids, index)))
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct VectorStore {
     pub dense: Vec<DenseVector>,
     pub quantized: Vec<QuantizedVector>,
@@ -91,6 +282,17 @@ pub struct VectorStore {
     // Maps a cluster id -> a list of vectors in that cluster
     pub ivf_index: Option<HashMap<usize, Vec<usize>>>, // we use Option because it doesn't exist initially
     pub centroids: Option<Vec<DenseVector>>,
+    pub hnsw_index: Option<HnswIndex>,
+    pub pq: Option<ProductQuantizer>,
+
+    // Set when the store was opened with `open_mmap` instead of built in
+    // memory; `vector()`/`len()` read through this when present.
+    pub mmap: Option<MmapVectors>,
+
+    // The notion of "closeness" every vector, centroid, and query in this
+    // store agrees on. Fixed at construction; `build_index`/`build_hnsw` and
+    // every search strategy read it from here rather than taking it per-call.
+    pub metric: Metric,
 }
 
 impl VectorStore {
@@ -101,25 +303,65 @@ impl VectorStore {
             dense: Vec::new(),
             ivf_index: None,
             centroids: None,
+            hnsw_index: None,
+            pq: None,
+            mmap: None,
+            metric: Metric::default(),
         }
     }
 
-    // When we add a vector, we split it into two views!
-    pub fn add(&mut self, full_vector: DenseVector) {
-        // 1. Create the Quantized version (e.g., take first 2 dims for this demo)
-        let q_view = QuantizedVector {
-            elements: full_vector.elements.iter().take(2).cloned().collect(),
-        };
+    /// Build a store that compares vectors under `metric` instead of the
+    /// default L2.
+    pub fn with_metric(metric: Metric) -> Self {
+        VectorStore {
+            metric,
+            ..VectorStore::new()
+        }
+    }
 
-        // 2. Store both
-        self.quantized.push(q_view);
+    pub fn add(&mut self, full_vector: DenseVector) {
         self.dense.push(full_vector);
     }
 
+    /// Number of stored vectors, whether held in memory or mmap-backed.
+    pub fn len(&self) -> usize {
+        match &self.mmap {
+            Some(region) => region.num_vectors,
+            None => self.dense.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// A zero-copy view of vector `i`'s elements, sourced from the mmap
+    /// region when the store was opened with `open_mmap`, or from `dense`
+    /// otherwise.
+    pub fn vector(&self, i: usize) -> &[f32] {
+        match &self.mmap {
+            Some(region) => region.vector(i),
+            None => &self.dense[i].elements,
+        }
+    }
+
     pub fn search<S: SearchStrategy>(&self, query_vec: &DenseVector, strategy: S) -> Option<(usize, f32)> {
         strategy.search(self, query_vec)
     }
 
+    // Index-building algorithms (kmeans, PQ training, HNSW insertion) all
+    // read `self.dense` directly rather than through `vector()`, so they
+    // need the full dataset resident in memory. Reports and refuses instead
+    // of training on nothing or panicking when `self.dense` is empty because
+    // the store was opened with `open_mmap`.
+    fn require_dense(&self, op: &str) -> bool {
+        if self.mmap.is_some() {
+            eprintln!("{op}: skipped -- build the index before saving/reopening via open_mmap, not after");
+            return false;
+        }
+        true
+    }
+
     pub fn save_to_disk(&self, path: &str) -> std::io::Result<()> {
         if self.dense.is_empty() {
             return Ok(());
@@ -143,11 +385,43 @@ impl VectorStore {
             writer.write_all(&bytes)?;
         }
 
+        // Persist the metric the store was built with, so reopening via
+        // either `load_from_disk` or `open_mmap` compares vectors the same
+        // way they were indexed -- see the doc comment on `metric`.
+        writer.write_all(&[self.metric.to_byte()])?;
+
+        // Persist a trained IVF index in a companion section so it can be
+        // reopened via either `load_from_disk` or `open_mmap` without
+        // retraining.
+        match (&self.centroids, &self.ivf_index) {
+            (Some(centroids), Some(index)) => {
+                writer.write_all(&[1u8])?;
+                writer.write_all(&(centroids.len() as u64).to_le_bytes())?;
+                for centroid in centroids {
+                    writer.write_all(&centroid.to_bytes())?;
+                }
+
+                writer.write_all(&(index.len() as u64).to_le_bytes())?;
+                for (&cluster_id, members) in index {
+                    writer.write_all(&(cluster_id as u64).to_le_bytes())?;
+                    writer.write_all(&(members.len() as u64).to_le_bytes())?;
+                    for &member in members {
+                        writer.write_all(&(member as u64).to_le_bytes())?;
+                    }
+                }
+            }
+            _ => writer.write_all(&[0u8])?,
+        }
+
         // Ensure all bytes are actually pushed to the physical disk
         writer.flush()?;
         Ok(())
     }
 
+    /// Load a store saved by `save_to_disk` fully into memory. A companion
+    /// IVF index saved alongside the vectors is restored too, so an index
+    /// built once can be reopened without retraining -- see `open_mmap` for
+    /// the mmap-backed equivalent.
     pub fn load_from_disk(path: &str) -> std::io::Result<Self> {
         let file = File::open(path)?;
         let mut reader = BufReader::new(file);
@@ -176,12 +450,55 @@ impl VectorStore {
             store.add(vec);
         }
 
+        let mut metric_buf = [0u8; 1];
+        reader.read_exact(&mut metric_buf)?;
+        store.metric = Metric::from_byte(metric_buf[0]);
+
+        if let Some((centroids, index)) = read_ivf_section(&mut reader, dim_size as usize)? {
+            store.centroids = Some(centroids);
+            store.ivf_index = Some(index);
+        }
+
+        Ok(store)
+    }
+
+    /// Open a store saved by `save_to_disk` as a read-only, mmap-backed
+    /// view: vector rows are faulted in from disk on demand via `vector()`
+    /// instead of being loaded up front, so the dataset no longer needs to
+    /// fit in RAM. A companion IVF index saved alongside the vectors is
+    /// restored too, so `IVFSearch` works without retraining.
+    pub fn open_mmap(path: &str) -> std::io::Result<Self> {
+        let region = MmapVectors::open(path)?;
+        let mut store = VectorStore::new();
+
+        let mut reader = BufReader::new(File::open(path)?);
+        reader.seek(SeekFrom::Start(region.data_end()))?;
+
+        let mut metric_buf = [0u8; 1];
+        reader.read_exact(&mut metric_buf)?;
+        store.metric = Metric::from_byte(metric_buf[0]);
+
+        if let Some((centroids, index)) = read_ivf_section(&mut reader, region.dim)? {
+            store.centroids = Some(centroids);
+            store.ivf_index = Some(index);
+        }
+
+        store.mmap = Some(region);
         Ok(store)
     }
 
     pub fn build_index(&mut self, num_clusters: usize, max_iters: usize) {
+        // kmeans trains over `self.dense`, which is empty on a store opened
+        // via `open_mmap` -- the real rows live in the mmap region instead.
+        // Indexes are built before a store is persisted/reopened, so refuse
+        // rather than silently training on nothing (or panicking inside
+        // kmeans, which `choose_multiple` would on an empty slice).
+        if !self.require_dense("build_index") {
+            return;
+        }
+
         // Train the centroids
-        let centroids = kmeans(&self.dense, num_clusters, max_iters);
+        let centroids = kmeans(&self.dense, num_clusters, max_iters, self.metric);
 
         // Setup the empty index
         let mut index: HashMap<usize, Vec<usize>> = HashMap::new();
@@ -192,7 +509,7 @@ impl VectorStore {
         // Assign every vector to a cluster: O(n)
         for (i, vector) in self.dense.iter().enumerate() {
             // Find the closest centroid index for this vector
-            let best_centroid_index = nearest_vector_index(&vector, &centroids);
+            let best_centroid_index = nearest_vector_index(&vector, &centroids, self.metric);
 
             // Add the ID to the bucket
             if let Some(bucket) = index.get_mut(&best_centroid_index) {
@@ -204,6 +521,76 @@ impl VectorStore {
         self.centroids = Some(centroids);
         self.ivf_index = Some(index);
     }
+
+    /// Build an HNSW graph index over every vector currently in the store.
+    ///
+    /// `m` bounds the neighbors kept per node per layer (layer 0 keeps `2*m`),
+    /// and `ef_construction` is the beam width used while wiring up each
+    /// insertion -- higher values build a higher-quality graph at the cost of
+    /// slower indexing.
+    pub fn build_hnsw(&mut self, m: usize, ef_construction: usize) {
+        // Same restriction as `build_index`: `HnswIndex::insert` reads
+        // neighbors out of `self.dense`, so on an mmap-backed store this
+        // would silently build an empty, useless graph (`0..self.dense.len()`
+        // never iterates) instead of reporting the problem.
+        if !self.require_dense("build_hnsw") {
+            return;
+        }
+
+        let mut index = HnswIndex::new(m, ef_construction);
+
+        for id in 0..self.dense.len() {
+            index.insert(self, id);
+        }
+
+        self.hnsw_index = Some(index);
+    }
+
+    /// Train a Product Quantizer over every vector currently in the store
+    /// and replace `quantized` with their encoded codes.
+    ///
+    /// `m` is the number of subspaces the vector dimension is split into,
+    /// and `k` is the codebook size per subspace (256 is the usual choice,
+    /// since it fits one byte per sub-code).
+    pub fn build_pq(&mut self, m: usize, k: usize, max_iters: usize) {
+        // PQ's asymmetric distance table is inherently an L2 (sum-of-squared-
+        // subspace-distances) construction; training it against a store
+        // indexed under cosine/inner-product would rank candidates in a
+        // different space than the IVF bucket they were assigned in. Refuse
+        // rather than silently mix metrics -- `IVFPQSearch` already treats a
+        // `None` pq the same as "not built yet".
+        if self.metric != Metric::L2 {
+            eprintln!(
+                "build_pq: skipped -- PQ only supports Metric::L2, store is configured for {:?}",
+                self.metric
+            );
+            return;
+        }
+
+        // `ProductQuantizer::train` indexes `vectors[0]` directly, which
+        // panics on an mmap-backed store (`self.dense` is empty there).
+        if !self.require_dense("build_pq") {
+            return;
+        }
+
+        // Same `vectors[0]` panic risk as above, but for a plain store that
+        // simply has nothing added to it yet -- `require_dense` only catches
+        // the mmap case, not this one.
+        if self.dense.is_empty() {
+            eprintln!("build_pq: skipped -- store has no vectors to train on");
+            return;
+        }
+
+        let pq = ProductQuantizer::train(&self.dense, m, k, max_iters);
+
+        self.quantized = self
+            .dense
+            .iter()
+            .map(|v| QuantizedVector { codes: pq.encode(v) })
+            .collect();
+
+        self.pq = Some(pq);
+    }
 }
 
 #[cfg(test)]
@@ -218,12 +605,8 @@ mod tests {
         let mut store = VectorStore::new();
 
         // 1. Create some distinct vectors
-        let v1 = DenseVector {
-            elements: vec![1.0, 2.0, 3.0],
-        };
-        let v2 = DenseVector {
-            elements: vec![4.0, 5.0, 6.0],
-        };
+        let v1 = DenseVector::new(vec![1.0, 2.0, 3.0]);
+        let v2 = DenseVector::new(vec![4.0, 5.0, 6.0]);
 
         store.add(v1.clone());
         store.add(v2.clone());
@@ -245,4 +628,216 @@ mod tests {
         // Cleanup: Delete the test file so we don't clutter your drive
         fs::remove_file(path).unwrap();
     }
+
+    // BruteForceSearch::search_k should return the k closest vectors ordered
+    // exactly like a plain sort over true distances -- it's the reference ranking
+    // every approximate strategy is judged against.
+    #[test]
+    fn test_brute_force_search_k_matches_sorted_order() {
+        let mut store = VectorStore::new();
+        for v in [
+            vec![0.0, 0.0],
+            vec![5.0, 0.0],
+            vec![1.0, 1.0],
+            vec![-3.0, 4.0],
+            vec![2.0, 2.0],
+        ] {
+            store.add(DenseVector::new(v));
+        }
+
+        let query = DenseVector::new(vec![0.0, 0.0]);
+
+        let mut expected: Vec<(usize, f32)> = (0..store.len())
+            .map(|i| (i, store.dense[i].distance(&query, store.metric)))
+            .collect();
+        expected.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+        let top_k = BruteForceSearch.search_k(&store, &query, 3);
+        let got_order: Vec<usize> = top_k.iter().map(|&(i, _)| i).collect();
+        let expected_order: Vec<usize> = expected.iter().take(3).map(|&(i, _)| i).collect();
+
+        assert_eq!(got_order, expected_order);
+    }
+
+    // Hand-wires `centroids`/`ivf_index` instead of calling `build_index`, so
+    // which vector lands in which bucket is deterministic instead of
+    // depending on kmeans' random initial centroids.
+    fn store_with_two_buckets(members: [Vec<f32>; 4], centroids: [Vec<f32>; 2]) -> VectorStore {
+        let mut store = VectorStore::new();
+        for v in members {
+            store.add(DenseVector::new(v));
+        }
+        store.centroids = Some(centroids.into_iter().map(DenseVector::new).collect());
+        store.ivf_index = Some(HashMap::from([(0, vec![0, 1]), (1, vec![2, 3])]));
+        store
+    }
+
+    // With `nprobe: 1`, IVF only scans the bucket whose centroid is nearest
+    // the query -- here that's bucket 0, even though the true nearest vector
+    // (idx 2) sits in bucket 1. Raising `nprobe` to 2 must recover it; this
+    // is the entire point of multi-probe search.
+    #[test]
+    fn test_ivf_nprobe_recovers_neighbor_missed_by_single_probe() {
+        let store = store_with_two_buckets(
+            [vec![-5.0, 0.0], vec![-6.0, 0.0], vec![5.0, 0.0], vec![6.0, 0.0]],
+            [vec![0.0, 0.0], vec![10.0, 0.0]],
+        );
+
+        let query = DenseVector::new(vec![4.0, 0.0]);
+
+        let single_probe = store.search(&query, IVFSearch { nprobe: 1 }).unwrap();
+        assert_eq!(single_probe.0, 0, "nprobe 1 should only see bucket 0's candidates");
+
+        let multi_probe = store.search(&query, IVFSearch { nprobe: 2 }).unwrap();
+        assert_eq!(multi_probe.0, 2, "nprobe 2 should recover the true nearest neighbor in bucket 1");
+    }
+
+    // `IVFSearch::search_k` with `nprobe` wide enough to cover every bucket
+    // should rank candidates exactly like a plain sort over true distances --
+    // the same guarantee `test_brute_force_search_k_matches_sorted_order`
+    // checks for brute force.
+    #[test]
+    fn test_ivf_search_k_matches_sorted_order_across_probed_buckets() {
+        let store = store_with_two_buckets(
+            [vec![-5.0, 0.0], vec![-1.0, 0.0], vec![4.5, 0.0], vec![1.5, 0.0]],
+            [vec![-3.0, 0.0], vec![3.0, 0.0]],
+        );
+
+        let query = DenseVector::new(vec![0.0, 0.0]);
+
+        let mut expected: Vec<(usize, f32)> = (0..store.len())
+            .map(|i| (i, store.dense[i].distance(&query, store.metric)))
+            .collect();
+        expected.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+        let top_k = IVFSearch { nprobe: 2 }.search_k(&store, &query, 3);
+        let got_order: Vec<usize> = top_k.iter().map(|&(i, _)| i).collect();
+        let expected_order: Vec<usize> = expected.iter().take(3).map(|&(i, _)| i).collect();
+
+        assert_eq!(got_order, expected_order);
+    }
+
+    // A store opened via `open_mmap` after `save_to_disk` should answer
+    // searches identically to the in-memory store it was saved from.
+    #[test]
+    fn test_mmap_round_trip_search() {
+        let path = "test_mmap_round_trip.bin";
+        let mut store = VectorStore::new();
+        for v in [vec![0.0, 0.0], vec![5.0, 0.0], vec![1.0, 1.0], vec![-3.0, 4.0]] {
+            store.add(DenseVector::new(v));
+        }
+
+        store.save_to_disk(path).unwrap();
+        let mmap_store = VectorStore::open_mmap(path).unwrap();
+
+        let query = DenseVector::new(vec![0.5, 0.5]);
+        let expected = store.search(&query, BruteForceSearch).unwrap();
+        let got = mmap_store.search(&query, BruteForceSearch).unwrap();
+
+        assert_eq!(got, expected);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    // IVFSearch's headline behavior -- reading only the probed bucket's rows
+    // via page faults -- must actually work against an mmap-backed store,
+    // not just BruteForceSearch.
+    #[test]
+    fn test_mmap_round_trip_ivf_search() {
+        let path = "test_mmap_round_trip_ivf.bin";
+        let mut store = VectorStore::new();
+        for v in [vec![0.0, 0.0], vec![5.0, 0.0], vec![1.0, 1.0], vec![-3.0, 4.0]] {
+            store.add(DenseVector::new(v));
+        }
+        store.build_index(2, 10);
+
+        store.save_to_disk(path).unwrap();
+        let mmap_store = VectorStore::open_mmap(path).unwrap();
+
+        let query = DenseVector::new(vec![0.5, 0.5]);
+        let expected = store.search(&query, IVFSearch { nprobe: 2 }).unwrap();
+        let got = mmap_store.search(&query, IVFSearch { nprobe: 2 }).unwrap();
+
+        assert_eq!(got, expected);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    // Under cosine distance, a same-direction vector should win over a
+    // smaller-L2-distance orthogonal one -- proof the metric, not just the
+    // magnitude, drives the ranking.
+    #[test]
+    fn test_cosine_metric_ignores_magnitude() {
+        let mut store = VectorStore::with_metric(Metric::Cosine);
+        store.add(DenseVector::new(vec![10.0, 0.0])); // same direction, large magnitude
+        store.add(DenseVector::new(vec![0.0, 1.0])); // orthogonal, closer in raw L2
+
+        let query = DenseVector::new(vec![1.0, 0.0]);
+        let (best_idx, best_dist) = store.search(&query, BruteForceSearch).unwrap();
+
+        assert_eq!(best_idx, 0);
+        assert!(best_dist < 1e-5);
+    }
+
+    // Under inner product, the vector maximizing the raw dot product should
+    // win even over one that's literally identical to the query (and so
+    // has zero L2 distance).
+    #[test]
+    fn test_inner_product_prefers_max_dot() {
+        let mut store = VectorStore::with_metric(Metric::InnerProduct);
+        store.add(DenseVector::new(vec![5.0, 5.0])); // highest dot product with the query
+        store.add(DenseVector::new(vec![1.0, 1.0])); // identical to the query, lower dot product
+
+        let query = DenseVector::new(vec![1.0, 1.0]);
+        let (best_idx, _) = store.search(&query, BruteForceSearch).unwrap();
+
+        assert_eq!(best_idx, 0);
+    }
+
+    // A non-L2 metric must survive a save/load round trip through both
+    // restore paths, or every search after reopening silently scores
+    // distances in the wrong space.
+    #[test]
+    fn test_save_and_load_preserves_metric() {
+        let path = "test_db_metric.bin";
+        let mut store = VectorStore::with_metric(Metric::Cosine);
+        store.add(DenseVector::new(vec![10.0, 0.0]));
+        store.add(DenseVector::new(vec![0.0, 1.0]));
+
+        store.save_to_disk(path).unwrap();
+
+        let loaded = VectorStore::load_from_disk(path).unwrap();
+        assert_eq!(loaded.metric, Metric::Cosine);
+
+        let mmap_store = VectorStore::open_mmap(path).unwrap();
+        assert_eq!(mmap_store.metric, Metric::Cosine);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    // A trained IVF index must survive a `load_from_disk` round trip too,
+    // not just `open_mmap` -- otherwise reopening in-memory silently loses
+    // the index and `IVFSearch` looks unbuilt.
+    #[test]
+    fn test_load_from_disk_restores_ivf_index() {
+        let path = "test_db_ivf_reload.bin";
+        let mut store = VectorStore::new();
+        for v in [vec![0.0, 0.0], vec![5.0, 0.0], vec![1.0, 1.0], vec![-3.0, 4.0]] {
+            store.add(DenseVector::new(v));
+        }
+        store.build_index(2, 10);
+
+        store.save_to_disk(path).unwrap();
+        let loaded = VectorStore::load_from_disk(path).unwrap();
+
+        assert!(loaded.centroids.is_some());
+        assert!(loaded.ivf_index.is_some());
+
+        let query = DenseVector::new(vec![0.5, 0.5]);
+        let expected = store.search(&query, IVFSearch { nprobe: 2 }).unwrap();
+        let got = loaded.search(&query, IVFSearch { nprobe: 2 }).unwrap();
+        assert_eq!(got, expected);
+
+        fs::remove_file(path).unwrap();
+    }
 }
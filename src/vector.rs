@@ -1,36 +1,141 @@
 #[derive(Debug, Clone)]
 pub struct DenseVector {
     pub elements: Vec<f32>,
+    // Cached at construction so cosine distance between `DenseVector`s --
+    // kmeans, IVF centroid ranking and HNSW under Metric::Cosine -- doesn't
+    // re-sum D floats on every single comparison. Brute-force and IVF
+    // candidate scoring read raw rows through `metric_distance` and
+    // recompute both norms per comparison. Kept in sync by `set_elements`
+    // whenever a vector's elements are changed in place.
+    norm: f32,
 }
 
+// A Product-Quantized view of a vector: one nearest-centroid byte per
+// subspace. The codebooks needed to decode/compare these live on the
+// `ProductQuantizer` shared across the `VectorStore`, not on the vector
+// itself -- see `pq::ProductQuantizer`.
 #[derive(Debug, Clone)]
 pub struct QuantizedVector {
-    pub elements: Vec<f32>,
+    pub codes: Vec<u8>,
+}
+
+/// Which notion of "closeness" an index is built and queried against.
+/// Every vector added to a store, every centroid trained for it, and every
+/// query against it must agree on one of these -- mixing them silently
+/// produces a nonsensical index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Metric {
+    /// Straight-line (Euclidean) distance. The default; smaller is closer.
+    #[default]
+    L2,
+    /// `1 - cosine_similarity`, for normalized-embedding workloads where
+    /// only direction matters, not magnitude.
+    Cosine,
+    /// Negative dot product, so "smaller is better" search logic still
+    /// finds the maximum-similarity vector (used by recommendation-style
+    /// workloads that want the highest raw dot product).
+    InnerProduct,
+}
+
+impl Metric {
+    /// Encodes as a single byte for `VectorStore::save_to_disk`'s companion
+    /// section; `from_byte` is its exact inverse.
+    pub fn to_byte(self) -> u8 {
+        match self {
+            Metric::L2 => 0,
+            Metric::Cosine => 1,
+            Metric::InnerProduct => 2,
+        }
+    }
+
+    pub fn from_byte(byte: u8) -> Self {
+        match byte {
+            1 => Metric::Cosine,
+            2 => Metric::InnerProduct,
+            _ => Metric::L2,
+        }
+    }
 }
 
 pub trait Distances {
-    fn distance(&self, other: &Self) -> f32;
+    fn distance(&self, other: &Self, metric: Metric) -> f32;
 }
 
 impl Distances for DenseVector {
-    fn distance(&self, other: &Self) -> f32 {
-        euclidean_distance(&self.elements, &other.elements)
+    fn distance(&self, other: &Self, metric: Metric) -> f32 {
+        match metric {
+            // Use the norms cached at construction instead of recomputing
+            // them from `self.elements`/`other.elements` on every call.
+            Metric::Cosine => cosine_distance_cached(&self.elements, self.norm, &other.elements, other.norm),
+            Metric::L2 | Metric::InnerProduct => metric_distance(&self.elements, &other.elements, metric),
+        }
     }
 }
 
-impl Distances for QuantizedVector {
-    fn distance(&self, other: &Self) -> f32 {
-        euclidean_distance(&self.elements, &other.elements)
+/// Dispatches to the right distance formula for `metric`. Operates on raw
+/// slices so it works equally for `DenseVector` elements and zero-copy
+/// mmap-backed rows.
+pub fn metric_distance(a: &[f32], b: &[f32], metric: Metric) -> f32 {
+    match metric {
+        Metric::L2 => euclidean_distance(a, b),
+        Metric::Cosine => cosine_distance(a, b),
+        Metric::InnerProduct => -dot(a, b),
     }
 }
 
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+fn norm(a: &[f32]) -> f32 {
+    dot(a, a).sqrt()
+}
+
+fn cosine_distance(a: &[f32], b: &[f32]) -> f32 {
+    cosine_distance_cached(a, norm(a), b, norm(b))
+}
+
+// Same formula as `cosine_distance`, but takes pre-computed norms instead of
+// re-summing `a`/`b` -- the fast path used for `DenseVector`s, which cache
+// their norm at construction.
+fn cosine_distance_cached(a: &[f32], norm_a: f32, b: &[f32], norm_b: f32) -> f32 {
+    let denom = norm_a * norm_b;
+    if denom == 0.0 {
+        return 1.0; // a zero vector has no direction; treat it as maximally dissimilar
+    }
+
+    1.0 - dot(a, b) / denom
+}
+
 impl DenseVector {
-    fn to_bytes(&self) -> Vec<u8> {
+    pub fn new(elements: Vec<f32>) -> Self {
+        let norm = norm(&elements);
+        DenseVector { elements, norm }
+    }
+
+    /// Replaces `elements` and recomputes the cached norm. Used by callers
+    /// that change a vector's elements in place (e.g. kmeans' cosine
+    /// centroid re-normalization) instead of constructing a new `DenseVector`.
+    pub(crate) fn set_elements(&mut self, elements: Vec<f32>) {
+        self.norm = norm(&elements);
+        self.elements = elements;
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
         self.elements
             .iter()
             .flat_map(|&x| x.to_le_bytes())
             .collect::<Vec<u8>>()
     }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let elements = bytes
+            .chunks_exact(4)
+            .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect();
+
+        DenseVector::new(elements)
+    }
 }
 
 fn euclidean_distance(a: &[f32], b: &[f32]) -> f32 {